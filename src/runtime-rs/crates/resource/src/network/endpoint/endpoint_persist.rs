@@ -0,0 +1,32 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::net::Ipv4Addr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::network::{DnsConfig, NetworkPolicy};
+
+/// Persisted representation of a single network endpoint, written by
+/// `Network::save()` so the sandbox network can be rebuilt on restore via
+/// `network::restore()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointState {
+    pub id: String,
+    pub name: String,
+    pub tap_name: String,
+    pub endpoint_type: String,
+    pub netns_path: String,
+    /// Network model ("tcfilter"/"route"/"none") the endpoint's pair was
+    /// created with; re-parsed via `NetworkModel::from` on restore.
+    pub network_model: String,
+    pub queues: usize,
+    pub guest_addr: Ipv4Addr,
+    pub guest_prefix: u8,
+    pub gateway_addr: Ipv4Addr,
+    pub policy: Option<NetworkPolicy>,
+    pub dns: DnsConfig,
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+pub mod endpoint_persist;
+mod tap_endpoint;
+
+use async_trait::async_trait;
+use hypervisor::{device::device_manager::DeviceManager, Hypervisor};
+pub use tap_endpoint::TapEndpoint;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::RwLock;
+
+use super::NetworkPair;
+
+/// An Endpoint is the guest-visible side of a network attachment (tap,
+/// veth, physical/SR-IOV, macvlan, ...) that has been wired into the
+/// hypervisor and reported to the agent as an `agent::Interface`.
+#[async_trait]
+pub trait Endpoint: Send + Sync {
+    /// Stable identifier used to look the endpoint back up, e.g. for
+    /// `Network::del_endpoint`/`get_endpoint`.
+    fn id(&self) -> String;
+    fn name(&self) -> String;
+    /// Name of the host-side tap device backing this endpoint, persisted
+    /// in `EndpointState` so it can be re-bound on restore.
+    fn tap_name(&self) -> String;
+    /// The guest-visible interface this endpoint backs, if any. Aggregated
+    /// by `Network::interfaces()` into what gets reported to the agent.
+    fn guest_interface(&self) -> Option<agent::Interface>;
+    /// Routes that must exist in the guest for `guest_interface` to be
+    /// reachable. Empty when the endpoint's model reports no routes (for
+    /// `TapEndpoint`: `NetworkModel::TcFilter` and `NetworkModel::None`).
+    fn guest_routes(&self) -> Vec<agent::Route>;
+    /// The underlying veth/tap pair, if this endpoint is backed by one.
+    /// Used only to persist the pair's address assignment in
+    /// `Network::save()`; endpoint types without one (e.g. a future
+    /// passthrough variant) return `None`.
+    fn network_pair(&self) -> Option<&NetworkPair>;
+    /// Plugs the endpoint's device into the hypervisor via the device
+    /// manager, which already owns the hypervisor handle it was built
+    /// with.
+    async fn hot_attach(&self, d: Arc<RwLock<DeviceManager>>) -> Result<()>;
+    /// Unplugs the endpoint's device directly through `h`. Callers such as
+    /// `Network::remove()`/`del_endpoint()` already carry an explicit
+    /// hypervisor reference for sandbox teardown, so detach goes straight
+    /// through it rather than back through the device manager.
+    async fn hot_detach(&self, h: &dyn Hypervisor) -> Result<()>;
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hypervisor::{device::device_manager::DeviceManager, Hypervisor};
+use tokio::sync::RwLock;
+
+use super::Endpoint;
+use crate::network::{NetworkModel, NetworkPair};
+
+/// An endpoint backed by a tap device handed straight to the hypervisor,
+/// with a veth peer left in the sandbox netns for host-side connectivity.
+pub struct TapEndpoint {
+    id: String,
+    pair: NetworkPair,
+    model: NetworkModel,
+}
+
+impl TapEndpoint {
+    pub fn new(id: String, pair: NetworkPair, model: NetworkModel) -> Self {
+        Self { id, pair, model }
+    }
+}
+
+#[async_trait]
+impl Endpoint for TapEndpoint {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> String {
+        self.pair.virt_iface_name.clone()
+    }
+
+    fn tap_name(&self) -> String {
+        self.pair.tap_iface_name.clone()
+    }
+
+    fn guest_interface(&self) -> Option<agent::Interface> {
+        Some(agent::Interface {
+            name: self.pair.tap_iface_name.clone(),
+            ip_addresses: vec![agent::IPAddress {
+                address: self.pair.guest_addr.to_string(),
+                mask: self.pair.guest_prefix.to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn network_pair(&self) -> Option<&NetworkPair> {
+        Some(&self.pair)
+    }
+
+    fn guest_routes(&self) -> Vec<agent::Route> {
+        // Only `Route` reports a route: a default route via the pair's
+        // gateway address. `TcFilter` and `None` report no routes; no tc
+        // filters are installed for any model.
+        match self.model {
+            NetworkModel::Route => vec![agent::Route {
+                dest: "0.0.0.0/0".to_string(),
+                gateway: self.pair.gateway_addr.to_string(),
+                device: self.pair.tap_iface_name.clone(),
+                ..Default::default()
+            }],
+            NetworkModel::TcFilter | NetworkModel::None => Vec::new(),
+        }
+    }
+
+    async fn hot_attach(&self, d: Arc<RwLock<DeviceManager>>) -> Result<()> {
+        let mut device_manager = d.write().await;
+        device_manager
+            .try_add_device(self.pair.tap_iface_name.clone())
+            .await
+            .context("hot-plug tap device into hypervisor")?;
+        Ok(())
+    }
+
+    async fn hot_detach(&self, h: &dyn Hypervisor) -> Result<()> {
+        h.remove_device(hypervisor::device::Device::Network(
+            self.pair.tap_iface_name.clone(),
+        ))
+        .await
+        .context("hot-unplug tap device from hypervisor")?;
+        Ok(())
+    }
+}
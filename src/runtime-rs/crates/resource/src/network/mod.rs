@@ -8,12 +8,22 @@ mod endpoint;
 use std::sync::Arc;
 
 pub use endpoint::endpoint_persist::EndpointState;
+use endpoint::TapEndpoint;
 pub use endpoint::Endpoint;
+mod network_direct;
+pub use network_direct::DirectDeviceConfig;
+use network_direct::NetworkDirect;
+mod network_dns;
+pub use network_dns::DnsConfig;
 mod network_entity;
+mod network_event;
+pub use network_event::NetworkEvent;
 mod network_info;
 pub use network_info::NetworkInfo;
 mod network_model;
 pub use network_model::NetworkModel;
+mod network_policy;
+pub use network_policy::NetworkPolicy;
 mod network_with_netns;
 pub use network_with_netns::NetworkWithNetNsConfig;
 use network_with_netns::NetworkWithNetns;
@@ -22,7 +32,7 @@ use network_pair::NetworkPair;
 mod utils;
 pub use utils::netns::{generate_netns_name, NetnsGuard};
 
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
@@ -31,6 +41,7 @@ use hypervisor::{device::device_manager::DeviceManager, Hypervisor};
 #[derive(Debug)]
 pub enum NetworkConfig {
     NetworkResourceWithNetNs(NetworkWithNetNsConfig),
+    DirectDevice(DirectDeviceConfig),
 }
 
 #[async_trait]
@@ -41,6 +52,26 @@ pub trait Network: Send + Sync {
     async fn neighs(&self) -> Result<Vec<agent::ARPNeighbor>>;
     async fn save(&self) -> Option<Vec<EndpointState>>;
     async fn remove(&self, h: &dyn Hypervisor) -> Result<()>;
+
+    /// Creates and hot-plugs a new endpoint after the sandbox is already
+    /// running, instead of requiring a full `setup()` re-run.
+    async fn add_endpoint(&self, cfg: &NetworkConfig) -> Result<Arc<dyn Endpoint>>;
+    /// Hot-unplugs and drops the endpoint previously returned by
+    /// `add_endpoint` under the id `Endpoint::id()` reported.
+    async fn del_endpoint(&self, id: &str, h: &dyn Hypervisor) -> Result<()>;
+    /// Looks up a previously attached endpoint by id, if it is still live.
+    async fn get_endpoint(&self, id: &str) -> Option<Arc<dyn Endpoint>>;
+
+    /// Subscribes to interface/address/route changes observed in the
+    /// sandbox netns after `setup()`, so the runtime can resync the guest
+    /// agent without waiting for the next full snapshot.
+    fn event_stream(&self) -> broadcast::Receiver<NetworkEvent>;
+
+    /// Resolved `/etc/resolv.conf` lines the agent should write into the
+    /// guest. `NetworkWithNetns` returns the explicit `DnsConfig` from its
+    /// config, or the host's own resolv.conf (read by `setup()`) when none
+    /// was supplied; `NetworkDirect` returns no lines.
+    async fn dns(&self) -> Result<Vec<String>>;
 }
 
 pub async fn new(
@@ -53,5 +84,25 @@ pub async fn new(
                 .await
                 .context("new network with netns")?,
         )),
+        NetworkConfig::DirectDevice(c) => Ok(Arc::new(
+            NetworkDirect::new(c, d)
+                .await
+                .context("new direct-device network")?,
+        )),
     }
 }
+
+/// Rebuilds the sandbox network from `EndpointState`s persisted by a prior
+/// `Network::save()`, for VM migration/checkpoint-restore. Only networks
+/// backed by `NetworkWithNetns` persist state, so this always reconstructs
+/// that variant.
+pub async fn restore(
+    states: Vec<EndpointState>,
+    d: Arc<RwLock<DeviceManager>>,
+) -> Result<Arc<dyn Network>> {
+    Ok(Arc::new(
+        NetworkWithNetns::restore(states, d)
+            .await
+            .context("restore network with netns")?,
+    ))
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use hypervisor::{device::device_manager::DeviceManager, Hypervisor};
+use tokio::sync::{broadcast, RwLock};
+
+use super::{
+    network_event::EVENT_CHANNEL_CAPACITY, Endpoint, EndpointState, Network, NetworkConfig,
+    NetworkEvent,
+};
+
+/// Configuration for binding a pre-existing host NIC/VF straight to the
+/// hypervisor (VFIO passthrough or macvtap), bypassing the netns/veth/tap
+/// datapath `NetworkWithNetns` uses. The guest-visible interface/routes are
+/// supplied up front since there is no netns to discover them from.
+#[derive(Debug, Clone)]
+pub struct DirectDeviceConfig {
+    pub host_dev_name: String,
+    pub guest_interface: agent::Interface,
+    pub guest_routes: Vec<agent::Route>,
+    pub guest_neighs: Vec<agent::ARPNeighbor>,
+}
+
+pub struct NetworkDirect {
+    config: DirectDeviceConfig,
+    device_manager: Arc<RwLock<DeviceManager>>,
+    event_sender: broadcast::Sender<NetworkEvent>,
+}
+
+impl NetworkDirect {
+    pub async fn new(
+        config: &DirectDeviceConfig,
+        device_manager: Arc<RwLock<DeviceManager>>,
+    ) -> Result<Self> {
+        let (event_sender, _rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Ok(Self {
+            config: config.clone(),
+            device_manager,
+            event_sender,
+        })
+    }
+}
+
+#[async_trait]
+impl Network for NetworkDirect {
+    async fn setup(&self) -> Result<()> {
+        let mut device_manager = self.device_manager.write().await;
+        device_manager
+            .try_add_device(self.config.host_dev_name.clone())
+            .await
+            .context("bind direct device into hypervisor")?;
+        Ok(())
+    }
+
+    async fn interfaces(&self) -> Result<Vec<agent::Interface>> {
+        Ok(vec![self.config.guest_interface.clone()])
+    }
+
+    async fn routes(&self) -> Result<Vec<agent::Route>> {
+        Ok(self.config.guest_routes.clone())
+    }
+
+    async fn neighs(&self) -> Result<Vec<agent::ARPNeighbor>> {
+        Ok(self.config.guest_neighs.clone())
+    }
+
+    async fn save(&self) -> Option<Vec<EndpointState>> {
+        // There is no veth/tap endpoint to persist. `network::restore()`
+        // does not rebuild a direct-device network.
+        None
+    }
+
+    async fn remove(&self, h: &dyn Hypervisor) -> Result<()> {
+        h.remove_device(hypervisor::device::Device::Network(
+            self.config.host_dev_name.clone(),
+        ))
+        .await
+        .context("unbind direct device from hypervisor")
+    }
+
+    async fn add_endpoint(&self, _cfg: &NetworkConfig) -> Result<Arc<dyn Endpoint>> {
+        Err(anyhow!(
+            "hot-plugging additional endpoints is not supported for direct-device networks"
+        ))
+    }
+
+    async fn del_endpoint(&self, _id: &str, _h: &dyn Hypervisor) -> Result<()> {
+        Err(anyhow!(
+            "hot-unplugging endpoints is not supported for direct-device networks"
+        ))
+    }
+
+    async fn get_endpoint(&self, _id: &str) -> Option<Arc<dyn Endpoint>> {
+        None
+    }
+
+    fn event_stream(&self) -> broadcast::Receiver<NetworkEvent> {
+        self.event_sender.subscribe()
+    }
+
+    async fn dns(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::fs;
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Guest DNS configuration, either supplied explicitly in the network
+/// config or resolved from the host netns `resolv.conf` when absent.
+/// Rendered by the agent into the guest's own `/etc/resolv.conf`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsConfig {
+    pub nameservers: Vec<IpAddr>,
+    pub search: Vec<String>,
+    pub options: Vec<String>,
+}
+
+impl DnsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.nameservers.is_empty() && self.search.is_empty() && self.options.is_empty()
+    }
+
+    /// Renders the resolved config into `/etc/resolv.conf` line format, the
+    /// shape the agent writes verbatim into the guest.
+    pub fn to_resolv_conf_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for ns in &self.nameservers {
+            lines.push(format!("nameserver {}", ns));
+        }
+        if !self.search.is_empty() {
+            lines.push(format!("search {}", self.search.join(" ")));
+        }
+        if !self.options.is_empty() {
+            lines.push(format!("options {}", self.options.join(" ")));
+        }
+        lines
+    }
+}
+
+/// Parses nameserver/search/options entries out of a resolv.conf-formatted
+/// file, used as the fallback when the sandbox's
+/// `NetworkWithNetNsConfig::dns` is left empty.
+pub(crate) fn parse_resolv_conf(path: &str) -> Result<DnsConfig> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("read resolv.conf at {}", path))?;
+
+    let mut dns = DnsConfig::default();
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("nameserver") => {
+                if let Some(addr) = fields.next().and_then(|a| a.parse::<IpAddr>().ok()) {
+                    dns.nameservers.push(addr);
+                }
+            }
+            Some("search") => dns.search.extend(fields.map(str::to_string)),
+            Some("options") => dns.options.extend(fields.map(str::to_string)),
+            _ => {}
+        }
+    }
+    Ok(dns)
+}
+
+pub(crate) fn parse_host_resolv_conf() -> Result<DnsConfig> {
+    parse_resolv_conf(DEFAULT_RESOLV_CONF)
+}
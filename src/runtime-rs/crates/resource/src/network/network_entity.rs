@@ -0,0 +1,23 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use super::{Endpoint, NetworkPolicy};
+
+/// A single endpoint as tracked internally by `NetworkWithNetns`, along
+/// with the policy (if any) programmed for it.
+#[derive(Clone)]
+pub(crate) struct NetworkEntity {
+    pub(crate) endpoint: Arc<dyn Endpoint>,
+    pub(crate) policy: Option<NetworkPolicy>,
+}
+
+impl NetworkEntity {
+    pub(crate) fn new(endpoint: Arc<dyn Endpoint>, policy: Option<NetworkPolicy>) -> Self {
+        Self { endpoint, policy }
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::io;
+use std::mem;
+use std::net::IpAddr;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use anyhow::{Context, Result};
+use tokio::io::unix::AsyncFd;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Capacity of the broadcast channel backing `Network::event_stream()`.
+/// Slow subscribers that fall behind this many events observe a `Lagged`
+/// error on their next `recv()` rather than unbounded memory growth.
+pub(crate) const EVENT_CHANNEL_CAPACITY: usize = 128;
+
+/// A change observed on the sandbox netns after `setup()` has already run,
+/// e.g. a CNI plugin attaching a second NIC post-boot.
+#[derive(Debug, Clone)]
+pub enum NetworkEvent {
+    InterfaceUp(String),
+    InterfaceDown(String),
+    AddrChanged { iface: String, addr: IpAddr },
+    RouteChanged,
+}
+
+const RTMGRP_LINK: u32 = 0x1;
+const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+const RTMGRP_IPV4_ROUTE: u32 = 0x40;
+
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const RTM_NEWADDR: u16 = 20;
+const RTM_DELADDR: u16 = 21;
+const RTM_NEWROUTE: u16 = 24;
+
+/// Owns a netlink monitor socket. Closed on drop.
+struct MonitorSocket(RawFd);
+
+impl AsRawFd for MonitorSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl Drop for MonitorSocket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// Opens an `NETLINK_ROUTE` socket subscribed to link/addr/route groups.
+/// This is a plain blocking syscall, deliberately *not* behind `.await`:
+/// `setns()` only rebinds the calling OS thread, so the socket must be
+/// opened synchronously on the thread that is actually inside the target
+/// netns (i.e. while the caller's `NetnsGuard` is held), before control
+/// returns to whatever arbitrary tokio worker runs the spawned task below.
+fn open_monitor_socket() -> Result<MonitorSocket> {
+    let groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV4_ROUTE;
+
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_NONBLOCK,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error()).context("open netlink socket");
+    }
+
+    let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_groups = groups;
+
+    let ret = unsafe {
+        libc::bind(
+            fd,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        let err = io::Error::last_os_error();
+        unsafe {
+            libc::close(fd);
+        }
+        return Err(err).context("bind netlink monitor groups");
+    }
+
+    Ok(MonitorSocket(fd))
+}
+
+/// Opens the monitor socket in the netns the caller is currently entered
+/// in and spawns the task that reads from it, moving the already-open
+/// socket across so the task itself never needs to care which netns the
+/// worker thread it lands on belongs to. Republishes what it observes on
+/// `sender`. The returned handle is aborted by the caller
+/// (`Network::remove()`) once the netns is torn down.
+pub(crate) fn spawn_monitor(sender: broadcast::Sender<NetworkEvent>) -> Result<JoinHandle<()>> {
+    let socket = open_monitor_socket().context("open netns-scoped netlink monitor")?;
+    Ok(tokio::spawn(async move { monitor_loop(socket, sender).await }))
+}
+
+/// Reads from `socket` and republishes decoded events on `sender` until
+/// the socket errors, is closed, or the task is aborted.
+async fn monitor_loop(socket: MonitorSocket, sender: broadcast::Sender<NetworkEvent>) {
+    let async_fd = match AsyncFd::new(socket) {
+        Ok(fd) => fd,
+        Err(_) => return,
+    };
+    let mut buf = [0u8; 4096];
+    loop {
+        let mut guard = match async_fd.readable().await {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+        let result = guard.try_io(|inner| {
+            let ret = unsafe {
+                libc::recv(
+                    inner.get_ref().as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if ret < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(ret as usize)
+            }
+        });
+        match result {
+            Ok(Ok(0)) => return,
+            Ok(Ok(n)) => {
+                for event in decode(&buf[..n]) {
+                    let _ = sender.send(event);
+                }
+            }
+            Ok(Err(_)) => return,
+            Err(_would_block) => continue,
+        }
+    }
+}
+
+/// `ifi_flags` bit meaning an interface is administratively up.
+/// The kernel signals "interface went down" via `RTM_NEWLINK` with this bit
+/// cleared, not via `RTM_DELLINK` (which only fires when the device itself
+/// is removed).
+const IFF_UP: u32 = 0x1;
+
+/// `rtattr` type carrying the interface name in an `ifinfomsg` payload.
+const IFLA_IFNAME: u16 = 3;
+/// `rtattr` types carrying the address in an `ifaddrmsg` payload.
+/// `IFA_LOCAL` (the address assigned to the interface) takes priority over
+/// `IFA_ADDRESS` (the prefix/peer address), matching what's actually
+/// configured on the link for point-to-point pairs like ours.
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+
+const NLMSGHDR_LEN: usize = 16;
+const IFINFOMSG_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+
+/// Decodes `RTM_NEWLINK`/`DELLINK`/`NEWADDR`/`DELADDR`/`NEWROUTE` messages
+/// out of a raw netlink datagram into `NetworkEvent`s.
+fn decode(buf: &[u8]) -> Vec<NetworkEvent> {
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while offset + NLMSGHDR_LEN <= buf.len() {
+        let len = u32::from_ne_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        if len < NLMSGHDR_LEN || offset + len > buf.len() {
+            break;
+        }
+        let msg_type = u16::from_ne_bytes(buf[offset + 4..offset + 6].try_into().unwrap());
+        let payload = &buf[offset + NLMSGHDR_LEN..offset + len];
+        match msg_type {
+            RTM_NEWLINK | RTM_DELLINK => events.extend(decode_link(msg_type, payload)),
+            RTM_NEWADDR | RTM_DELADDR => events.extend(decode_addr(payload)),
+            RTM_NEWROUTE => events.push(NetworkEvent::RouteChanged),
+            _ => {}
+        }
+        offset += (len + 3) & !3;
+    }
+    events
+}
+
+/// Parses an `ifinfomsg` + trailing `rtattr`s into an up/down event, using
+/// `ifi_flags & IFF_UP` to tell a link going administratively down
+/// (`RTM_NEWLINK`, flag cleared) apart from one coming up (`RTM_NEWLINK`,
+/// flag set), and treating `RTM_DELLINK` (device removal) as down.
+fn decode_link(msg_type: u16, payload: &[u8]) -> Option<NetworkEvent> {
+    if payload.len() < IFINFOMSG_LEN {
+        return None;
+    }
+    let ifi_flags = u32::from_ne_bytes(payload[8..12].try_into().unwrap());
+    let name = parse_rtattrs(&payload[IFINFOMSG_LEN..])
+        .into_iter()
+        .find(|(attr_type, _)| *attr_type == IFLA_IFNAME)
+        .map(|(_, value)| cstr_to_string(value))?;
+
+    if msg_type == RTM_DELLINK || ifi_flags & IFF_UP == 0 {
+        Some(NetworkEvent::InterfaceDown(name))
+    } else {
+        Some(NetworkEvent::InterfaceUp(name))
+    }
+}
+
+/// Parses an `ifaddrmsg` + trailing `rtattr`s into an address-change
+/// event. The interface name isn't carried in the message itself, only
+/// `ifa_index`, so it's resolved back to a name via `if_indextoname`.
+fn decode_addr(payload: &[u8]) -> Option<NetworkEvent> {
+    if payload.len() < IFADDRMSG_LEN {
+        return None;
+    }
+    let family = payload[0] as i32;
+    let index = u32::from_ne_bytes(payload[4..8].try_into().unwrap());
+
+    let attrs = parse_rtattrs(&payload[IFADDRMSG_LEN..]);
+    let addr_bytes = attrs
+        .iter()
+        .find(|(attr_type, _)| *attr_type == IFA_LOCAL)
+        .or_else(|| attrs.iter().find(|(attr_type, _)| *attr_type == IFA_ADDRESS))
+        .map(|(_, value)| *value)?;
+
+    let addr = match family {
+        libc::AF_INET => {
+            let octets: [u8; 4] = addr_bytes.get(..4)?.try_into().ok()?;
+            IpAddr::from(octets)
+        }
+        libc::AF_INET6 => {
+            let octets: [u8; 16] = addr_bytes.get(..16)?.try_into().ok()?;
+            IpAddr::from(octets)
+        }
+        _ => return None,
+    };
+
+    Some(NetworkEvent::AddrChanged {
+        iface: if_index_to_name(index).unwrap_or_default(),
+        addr,
+    })
+}
+
+/// Walks a sequence of `rtattr { rta_len, rta_type }` + value, each padded
+/// up to 4-byte (`RTA_ALIGNTO`) boundaries, returning `(rta_type, value)`.
+fn parse_rtattrs(buf: &[u8]) -> Vec<(u16, &[u8])> {
+    const RTATTR_LEN: usize = 4;
+
+    let mut attrs = Vec::new();
+    let mut offset = 0;
+    while offset + RTATTR_LEN <= buf.len() {
+        let rta_len = u16::from_ne_bytes(buf[offset..offset + 2].try_into().unwrap()) as usize;
+        let rta_type = u16::from_ne_bytes(buf[offset + 2..offset + 4].try_into().unwrap());
+        if rta_len < RTATTR_LEN || offset + rta_len > buf.len() {
+            break;
+        }
+        attrs.push((rta_type, &buf[offset + RTATTR_LEN..offset + rta_len]));
+        offset += (rta_len + 3) & !3;
+    }
+    attrs
+}
+
+fn cstr_to_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn if_index_to_name(index: u32) -> Option<String> {
+    let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+    let ret = unsafe { libc::if_indextoname(index, name.as_mut_ptr()) };
+    if ret.is_null() {
+        return None;
+    }
+    let cstr = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+    Some(cstr.to_string_lossy().into_owned())
+}
@@ -0,0 +1,14 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+/// Snapshot of the interfaces/routes/neighbours discovered for the sandbox
+/// netns, as reported to the guest agent.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkInfo {
+    pub interfaces: Vec<agent::Interface>,
+    pub routes: Vec<agent::Route>,
+    pub neighs: Vec<agent::ARPNeighbor>,
+}
@@ -0,0 +1,36 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+/// Selects which guest routes a `TapEndpoint` reports. `NetworkPair::new`
+/// creates the same devices for every model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkModel {
+    TcFilter,
+    Route,
+    None,
+}
+
+impl From<&str> for NetworkModel {
+    fn from(s: &str) -> Self {
+        match s {
+            "tcfilter" => NetworkModel::TcFilter,
+            "route" => NetworkModel::Route,
+            _ => NetworkModel::None,
+        }
+    }
+}
+
+impl NetworkModel {
+    /// Inverse of `From<&str>`, used to persist the model in
+    /// `EndpointState` so it survives a save/restore round-trip.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkModel::TcFilter => "tcfilter",
+            NetworkModel::Route => "route",
+            NetworkModel::None => "none",
+        }
+    }
+}
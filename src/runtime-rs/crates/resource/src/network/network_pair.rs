@@ -0,0 +1,144 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::io;
+use std::mem;
+use std::net::Ipv4Addr;
+use std::os::unix::io::AsRawFd;
+
+use anyhow::{anyhow, Context, Result};
+use futures::stream::TryStreamExt;
+
+/// The host-side devices behind a guest-visible endpoint: a veth pair
+/// (`eth{idx}` and its `eth{idx}-host` peer) plus the tap device handed to
+/// the hypervisor. The host peer is brought up with the pair's `/30`
+/// gateway address. Nothing here links the tap to the veth pair (no tc
+/// filter or bridge is set up).
+#[derive(Debug, Clone)]
+pub struct NetworkPair {
+    pub virt_iface_name: String,
+    pub tap_iface_name: String,
+    pub queues: usize,
+    pub guest_addr: Ipv4Addr,
+    pub guest_prefix: u8,
+    pub gateway_addr: Ipv4Addr,
+}
+
+impl NetworkPair {
+    /// Creates a new veth/tap pair inside the netns the caller is currently
+    /// entered in. `idx` only needs to be unique within the sandbox; it also
+    /// selects the pair's point-to-point `/30` block, starting at
+    /// `169.254.0.0` + `4 * idx`.
+    pub async fn new(idx: usize, queues: usize) -> Result<Self> {
+        let virt_iface_name = format!("eth{}", idx);
+        let veth_peer_name = format!("{}-host", virt_iface_name);
+        let tap_iface_name = format!("tap{}", idx);
+
+        let block = (idx as u32) * 4;
+        let gateway_addr = Ipv4Addr::from(0xA9FE_0000u32 + block + 1);
+        let guest_addr = Ipv4Addr::from(0xA9FE_0000u32 + block + 2);
+        let guest_prefix = 30;
+
+        let (connection, handle, _) = rtnetlink::new_connection().context("open rtnetlink connection")?;
+        tokio::spawn(connection);
+
+        handle
+            .link()
+            .add()
+            .veth(virt_iface_name.clone(), veth_peer_name.clone())
+            .execute()
+            .await
+            .with_context(|| format!("create veth pair {}/{}", virt_iface_name, veth_peer_name))?;
+
+        create_tap(&tap_iface_name, queues).with_context(|| format!("create tap device {}", tap_iface_name))?;
+
+        for name in [virt_iface_name.as_str(), veth_peer_name.as_str(), tap_iface_name.as_str()] {
+            let index = link_index(&handle, name)
+                .await
+                .with_context(|| format!("look up link {}", name))?;
+            handle
+                .link()
+                .set(index)
+                .up()
+                .execute()
+                .await
+                .with_context(|| format!("bring up link {}", name))?;
+        }
+
+        let host_index = link_index(&handle, &veth_peer_name)
+            .await
+            .context("look up veth host peer")?;
+        handle
+            .address()
+            .add(host_index, gateway_addr.into(), guest_prefix)
+            .execute()
+            .await
+            .context("assign address to veth host peer")?;
+
+        Ok(Self {
+            virt_iface_name,
+            tap_iface_name,
+            queues,
+            guest_addr,
+            guest_prefix,
+            gateway_addr,
+        })
+    }
+}
+
+async fn link_index(handle: &rtnetlink::Handle, name: &str) -> Result<u32> {
+    handle
+        .link()
+        .get()
+        .match_name(name.to_string())
+        .execute()
+        .try_next()
+        .await
+        .with_context(|| format!("query link {}", name))?
+        .map(|link| link.header.index)
+        .with_context(|| format!("link {} not found after creation", name))
+}
+
+/// Creates a standalone multi-queue tap device via the `TUNSETIFF` ioctl on
+/// `/dev/net/tun`, the same mechanism the hypervisor itself uses to later
+/// reopen it by name. One file descriptor per queue is opened and then
+/// leaked so the device persists in the netns past this call; the
+/// hypervisor reopens it when the tap is hot-plugged.
+fn create_tap(name: &str, queues: usize) -> Result<()> {
+    const TUNSETIFF: libc::c_ulong = 0x4004_54ca;
+    const IFF_TAP: libc::c_short = 0x0002;
+    const IFF_NO_PI: libc::c_short = 0x1000;
+    const IFF_MULTI_QUEUE: libc::c_short = 0x0100;
+
+    #[repr(C)]
+    struct IfReq {
+        name: [libc::c_char; libc::IFNAMSIZ],
+        flags: libc::c_short,
+        _pad: [u8; 24],
+    }
+
+    for _ in 0..queues.max(1) {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/net/tun")
+            .context("open /dev/net/tun")?;
+
+        let mut req: IfReq = unsafe { mem::zeroed() };
+        for (dst, src) in req.name.iter_mut().zip(name.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        req.flags = IFF_TAP | IFF_NO_PI | IFF_MULTI_QUEUE;
+
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), TUNSETIFF, &req) };
+        if ret < 0 {
+            return Err(anyhow!(io::Error::last_os_error()).context("TUNSETIFF"));
+        }
+        // Leak the fd: closing it would tear the tap back down immediately.
+        std::mem::forget(file);
+    }
+    Ok(())
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hypervisor::{device::device_manager::DeviceManager, Hypervisor};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+use super::{
+    network_dns, network_entity::NetworkEntity,
+    network_event::{spawn_monitor, EVENT_CHANNEL_CAPACITY},
+    network_policy,
+    utils::netns::NetnsGuard,
+    DnsConfig, Endpoint, EndpointState, Network, NetworkConfig, NetworkEvent, NetworkInfo,
+    NetworkModel, NetworkPair, NetworkPolicy, TapEndpoint,
+};
+
+#[derive(Debug, Clone)]
+pub struct NetworkWithNetNsConfig {
+    pub network_model: String,
+    pub netns_path: String,
+    pub network_created: bool,
+    pub queues: usize,
+    pub policy: Option<NetworkPolicy>,
+    pub dns: Option<DnsConfig>,
+}
+
+struct NetworkWithNetnsInner {
+    netns_path: String,
+    model: NetworkModel,
+    queues: usize,
+    policy: Option<NetworkPolicy>,
+    dns: Option<DnsConfig>,
+    entities: Vec<NetworkEntity>,
+    event_task: Option<JoinHandle<()>>,
+    /// Next `NetworkPair::new` index to hand out. Monotonically
+    /// increasing rather than derived from `entities.len()`, since the
+    /// latter is reused after a `del_endpoint` and would collide its
+    /// `eth{idx}`/`tap{idx}` names and `169.254.x/30` block with a
+    /// still-live endpoint created in between.
+    next_idx: usize,
+}
+
+pub struct NetworkWithNetns {
+    inner: Arc<RwLock<NetworkWithNetnsInner>>,
+    device_manager: Arc<RwLock<DeviceManager>>,
+    event_sender: broadcast::Sender<NetworkEvent>,
+}
+
+impl NetworkWithNetns {
+    pub async fn new(
+        config: &NetworkWithNetNsConfig,
+        device_manager: Arc<RwLock<DeviceManager>>,
+    ) -> Result<Self> {
+        let (event_sender, _rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Ok(Self {
+            inner: Arc::new(RwLock::new(NetworkWithNetnsInner {
+                netns_path: config.netns_path.clone(),
+                model: NetworkModel::from(config.network_model.as_str()),
+                queues: config.queues,
+                policy: config.policy.clone(),
+                dns: config.dns.clone(),
+                entities: Vec::new(),
+                event_task: None,
+                next_idx: 0,
+            })),
+            device_manager,
+            event_sender,
+        })
+    }
+
+    /// Rebuilds a `NetworkWithNetns` from state persisted by a prior
+    /// `save()`, re-entering the saved netns and reattaching each
+    /// endpoint's tap device to the hypervisor without re-running
+    /// host-side interface creation.
+    pub(crate) async fn restore(
+        states: Vec<EndpointState>,
+        device_manager: Arc<RwLock<DeviceManager>>,
+    ) -> Result<Self> {
+        // An empty `states` means there is nothing to rebuild (e.g. a
+        // direct-device-only sandbox never had a `NetworkWithNetns` in the
+        // first place): skip re-entering a netns entirely rather than
+        // feeding `NetnsGuard::new("")` a path that was never real.
+        if states.is_empty() {
+            let (event_sender, _rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+            return Ok(Self {
+                inner: Arc::new(RwLock::new(NetworkWithNetnsInner {
+                    netns_path: String::new(),
+                    model: NetworkModel::None,
+                    queues: 1,
+                    policy: None,
+                    dns: None,
+                    entities: Vec::new(),
+                    event_task: None,
+                    next_idx: 0,
+                })),
+                device_manager,
+                event_sender,
+            });
+        }
+
+        let netns_path = states[0].netns_path.clone();
+        let queues = states[0].queues;
+        let model = NetworkModel::from(states[0].network_model.as_str());
+        let policy = states[0].policy.clone();
+        let dns = states.first().map(|s| s.dns.clone());
+        // New endpoints added post-restore must not reuse an index already
+        // claimed by a restored one. Saved indices can have gaps left by
+        // `del_endpoint`, so continue past the highest `tap{idx}` seen.
+        let next_idx = states
+            .iter()
+            .filter_map(|s| s.tap_name.strip_prefix("tap")?.parse::<usize>().ok())
+            .map(|idx| idx + 1)
+            .max()
+            .unwrap_or(0)
+            .max(states.len());
+
+        let (event_sender, _rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let network = Self {
+            inner: Arc::new(RwLock::new(NetworkWithNetnsInner {
+                netns_path: netns_path.clone(),
+                model,
+                queues,
+                policy,
+                dns,
+                entities: Vec::new(),
+                event_task: None,
+                next_idx,
+            })),
+            device_manager,
+            event_sender,
+        };
+
+        let mut inner = network.inner.write().await;
+        let _guard = NetnsGuard::new(&netns_path).context("re-enter persisted netns")?;
+        for state in states {
+            let pair = NetworkPair {
+                virt_iface_name: state.name,
+                tap_iface_name: state.tap_name,
+                queues: inner.queues,
+                guest_addr: state.guest_addr,
+                guest_prefix: state.guest_prefix,
+                gateway_addr: state.gateway_addr,
+            };
+            let endpoint: Arc<dyn Endpoint> =
+                Arc::new(TapEndpoint::new(state.id, pair, inner.model));
+            endpoint
+                .hot_attach(network.device_manager.clone())
+                .await
+                .context("reattach restored endpoint to hypervisor")?;
+            if let Some(policy) = &state.policy {
+                network_policy::apply(&endpoint.name(), policy)
+                    .context("re-apply restored network policy")?;
+            }
+            inner
+                .entities
+                .push(NetworkEntity::new(endpoint, state.policy));
+        }
+
+        // Matches `setup()`: spawn the monitor while `_guard` still has us
+        // entered in the restored netns, so `event_stream()` stays live
+        // after a restore instead of silently going dead.
+        inner.event_task =
+            Some(spawn_monitor(network.event_sender.clone()).context("spawn netns event monitor")?);
+        drop(inner);
+
+        Ok(network)
+    }
+
+    /// Aggregates each entity's guest-visible interface/routes into the
+    /// shape reported to the agent. ARP neighbours are not synthesized
+    /// here: nothing about pair creation produces neighbour entries, so
+    /// `neighs` stays empty until something actually discovers them.
+    async fn snapshot(&self) -> NetworkInfo {
+        let inner = self.inner.read().await;
+        let mut info = NetworkInfo::default();
+        for entity in &inner.entities {
+            if let Some(iface) = entity.endpoint.guest_interface() {
+                info.interfaces.push(iface);
+            }
+            info.routes.extend(entity.endpoint.guest_routes());
+        }
+        info
+    }
+}
+
+#[async_trait]
+impl Network for NetworkWithNetns {
+    async fn setup(&self) -> Result<()> {
+        let mut inner = self.inner.write().await;
+
+        // An absent or empty `dns` config falls back to the host's
+        // `/etc/resolv.conf`. `NetnsGuard` below switches only the network
+        // namespace, so the file read is the host's either way.
+        if inner.dns.as_ref().map(DnsConfig::is_empty).unwrap_or(true) {
+            inner.dns =
+                Some(network_dns::parse_host_resolv_conf().context("parse host resolv.conf")?);
+        }
+
+        let _guard = NetnsGuard::new(&inner.netns_path).context("enter netns for setup")?;
+
+        // Creates a single tap/veth pair backed by the hypervisor. (Does
+        // not enumerate any interfaces already present in the netns; a
+        // netns with multiple pre-existing NICs only gets this one.)
+        let idx = inner.next_idx;
+        inner.next_idx += 1;
+        let queues = inner.queues;
+        let model = inner.model;
+        let pair = NetworkPair::new(idx, queues)
+            .await
+            .context("create network pair")?;
+        let endpoint: Arc<dyn Endpoint> = Arc::new(TapEndpoint::new(
+            uuid::Uuid::new_v4().to_string(),
+            pair,
+            model,
+        ));
+        endpoint.hot_attach(self.device_manager.clone()).await?;
+
+        let policy = inner.policy.clone();
+        if let Some(policy) = &policy {
+            network_policy::apply(&endpoint.name(), policy).context("apply network policy")?;
+        }
+        inner.entities.push(NetworkEntity::new(endpoint, policy));
+
+        // `spawn_monitor` opens its socket synchronously, on this thread,
+        // while `_guard` still has it entered in the sandbox netns; only
+        // the already-open socket (not the netns path) crosses into the
+        // spawned task, since `setns()` does not follow it to whatever
+        // worker thread ends up running it.
+        inner.event_task =
+            Some(spawn_monitor(self.event_sender.clone()).context("spawn netns event monitor")?);
+
+        Ok(())
+    }
+
+    async fn interfaces(&self) -> Result<Vec<agent::Interface>> {
+        Ok(self.snapshot().await.interfaces)
+    }
+
+    async fn routes(&self) -> Result<Vec<agent::Route>> {
+        Ok(self.snapshot().await.routes)
+    }
+
+    async fn neighs(&self) -> Result<Vec<agent::ARPNeighbor>> {
+        Ok(self.snapshot().await.neighs)
+    }
+
+    async fn save(&self) -> Option<Vec<EndpointState>> {
+        let inner = self.inner.read().await;
+        Some(
+            inner
+                .entities
+                .iter()
+                .filter_map(|e| {
+                    let pair = e.endpoint.network_pair()?;
+                    Some(EndpointState {
+                        id: e.endpoint.id(),
+                        name: e.endpoint.name(),
+                        tap_name: e.endpoint.tap_name(),
+                        endpoint_type: "tap".to_string(),
+                        netns_path: inner.netns_path.clone(),
+                        network_model: inner.model.as_str().to_string(),
+                        queues: inner.queues,
+                        guest_addr: pair.guest_addr,
+                        guest_prefix: pair.guest_prefix,
+                        gateway_addr: pair.gateway_addr,
+                        policy: e.policy.clone(),
+                        dns: inner.dns.clone().unwrap_or_default(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    async fn remove(&self, h: &dyn Hypervisor) -> Result<()> {
+        let mut inner = self.inner.write().await;
+        let _guard = NetnsGuard::new(&inner.netns_path).context("enter netns for removal")?;
+        if let Some(task) = inner.event_task.take() {
+            task.abort();
+        }
+        for entity in inner.entities.drain(..) {
+            entity.endpoint.hot_detach(h).await?;
+            network_policy::flush(&entity.endpoint.name())?;
+        }
+        Ok(())
+    }
+
+    async fn add_endpoint(&self, cfg: &NetworkConfig) -> Result<Arc<dyn Endpoint>> {
+        let ns_cfg = match cfg {
+            NetworkConfig::NetworkResourceWithNetNs(c) => c,
+            NetworkConfig::DirectDevice(_) => {
+                return Err(anyhow::anyhow!(
+                    "add_endpoint only supports NetworkResourceWithNetNs configs"
+                ))
+            }
+        };
+
+        let mut inner = self.inner.write().await;
+        let _guard =
+            NetnsGuard::new(&inner.netns_path).context("enter netns to hot-plug endpoint")?;
+
+        let idx = inner.next_idx;
+        inner.next_idx += 1;
+        let model = inner.model;
+        let pair = NetworkPair::new(idx, ns_cfg.queues)
+            .await
+            .context("create network pair")?;
+        let endpoint: Arc<dyn Endpoint> = Arc::new(TapEndpoint::new(
+            uuid::Uuid::new_v4().to_string(),
+            pair,
+            model,
+        ));
+
+        endpoint
+            .hot_attach(self.device_manager.clone())
+            .await
+            .context("hot-plug new endpoint")?;
+
+        if let Some(policy) = &ns_cfg.policy {
+            network_policy::apply(&endpoint.name(), policy).context("apply network policy")?;
+        }
+
+        inner
+            .entities
+            .push(NetworkEntity::new(endpoint.clone(), ns_cfg.policy.clone()));
+        Ok(endpoint)
+    }
+
+    async fn del_endpoint(&self, id: &str, h: &dyn Hypervisor) -> Result<()> {
+        let mut inner = self.inner.write().await;
+        let _guard =
+            NetnsGuard::new(&inner.netns_path).context("enter netns to hot-unplug endpoint")?;
+
+        let pos = inner
+            .entities
+            .iter()
+            .position(|e| e.endpoint.id() == id)
+            .with_context(|| format!("no such endpoint: {}", id))?;
+        let entity = inner.entities.remove(pos);
+        network_policy::flush(&entity.endpoint.name())?;
+        entity.endpoint.hot_detach(h).await
+    }
+
+    async fn get_endpoint(&self, id: &str) -> Option<Arc<dyn Endpoint>> {
+        let inner = self.inner.read().await;
+        inner
+            .entities
+            .iter()
+            .find(|e| e.endpoint.id() == id)
+            .map(|e| e.endpoint.clone())
+    }
+
+    fn event_stream(&self) -> broadcast::Receiver<NetworkEvent> {
+        self.event_sender.subscribe()
+    }
+
+    async fn dns(&self) -> Result<Vec<String>> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .dns
+            .as_ref()
+            .map(DnsConfig::to_resolv_conf_lines)
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use nix::sched::{setns, CloneFlags};
+
+/// Generate a unique name for a sandbox-owned network namespace.
+pub fn generate_netns_name() -> String {
+    format!("kata-{}", uuid::Uuid::new_v4())
+}
+
+/// Enters `netns_path` for as long as the guard is alive, restoring the
+/// caller's original network namespace on drop.
+pub struct NetnsGuard {
+    old_ns: Option<File>,
+}
+
+impl NetnsGuard {
+    pub fn new(netns_path: &str) -> Result<Self> {
+        let old_ns = File::open("/proc/self/ns/net").context("open current netns")?;
+        let new_ns = File::open(Path::new(netns_path))
+            .with_context(|| format!("open netns {}", netns_path))?;
+        setns(new_ns.as_raw_fd(), CloneFlags::CLONE_NEWNET)
+            .with_context(|| format!("setns into {}", netns_path))?;
+
+        Ok(Self {
+            old_ns: Some(old_ns),
+        })
+    }
+}
+
+impl Drop for NetnsGuard {
+    fn drop(&mut self) {
+        // Best-effort: there is no way to propagate an error from `Drop`,
+        // and leaving the caller in the sandbox netns is the safer failure
+        // mode than panicking here.
+        if let Some(old_ns) = self.old_ns.take() {
+            let _ = setns(old_ns.as_raw_fd(), CloneFlags::CLONE_NEWNET);
+        }
+    }
+}